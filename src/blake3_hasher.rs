@@ -0,0 +1,45 @@
+//! BLAKE3-backed [`Hasher`](crate::traits::Hasher) implementation, with
+//! keyed and derive-key domain separation built in.
+
+use crate::{traits::Hasher, H256};
+
+pub struct Blake3Hasher(blake3::Hasher);
+
+impl Default for Blake3Hasher {
+    fn default() -> Self {
+        Blake3Hasher(blake3::Hasher::new())
+    }
+}
+
+impl Blake3Hasher {
+    /// Builds a hasher in BLAKE3's keyed-hash mode, so every digest it
+    /// produces is bound to `key` -- two trees hashed with different keys
+    /// never collide, even over the same leaves.
+    pub fn new_keyed(key: [u8; 32]) -> Self {
+        Blake3Hasher(blake3::Hasher::new_keyed(&key))
+    }
+
+    /// Builds a hasher in BLAKE3's derive-key mode, deriving its key from
+    /// `context` (a unique, application-specific string, per the upstream
+    /// recommendation of including a date and use case). Use this instead of
+    /// [`new_keyed`](Self::new_keyed) when the domain-separation key itself
+    /// doesn't need to be kept secret.
+    pub fn new_derive_key(context: &str) -> Self {
+        Blake3Hasher(blake3::Hasher::new_derive_key(context))
+    }
+}
+
+impl Hasher for Blake3Hasher {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(self) -> H256 {
+        let digest = self.0.finalize();
+        (*digest.as_bytes()).into()
+    }
+
+    fn new_keyed(key: &H256) -> Self {
+        Self::new_keyed((*key).into())
+    }
+}
@@ -0,0 +1,53 @@
+//! In-memory [`Store`] backed by a pair of `BTreeMap`s.
+
+use crate::{collections::BTreeMap, error::Error, traits::Store, H256};
+
+/// The default, fully in-memory [`Store`] implementation.
+///
+/// `N` mirrors the tree height in bytes to keep the type signature aligned
+/// with [`SparseMerkleTree`](crate::tree::SparseMerkleTree); `DefaultStore` itself does not
+/// need it.
+#[derive(Debug, Clone)]
+pub struct DefaultStore<V, const N: usize> {
+    branches_map: BTreeMap<H256, (H256, H256)>,
+    leaves_map: BTreeMap<H256, V>,
+}
+
+impl<V, const N: usize> Default for DefaultStore<V, N> {
+    fn default() -> Self {
+        DefaultStore {
+            branches_map: BTreeMap::new(),
+            leaves_map: BTreeMap::new(),
+        }
+    }
+}
+
+impl<V: Clone, const N: usize> Store<V, N> for DefaultStore<V, N> {
+    fn get_branch(&self, node: &H256) -> Result<Option<(H256, H256)>, Error> {
+        Ok(self.branches_map.get(node).copied())
+    }
+
+    fn get_leaf(&self, leaf_key: &H256) -> Result<Option<V>, Error> {
+        Ok(self.leaves_map.get(leaf_key).cloned())
+    }
+
+    fn insert_branch(&mut self, node: H256, left: H256, right: H256) -> Result<(), Error> {
+        self.branches_map.insert(node, (left, right));
+        Ok(())
+    }
+
+    fn insert_leaf(&mut self, leaf_key: H256, value: V) -> Result<(), Error> {
+        self.leaves_map.insert(leaf_key, value);
+        Ok(())
+    }
+
+    fn remove_branch(&mut self, node: &H256) -> Result<(), Error> {
+        self.branches_map.remove(node);
+        Ok(())
+    }
+
+    fn remove_leaf(&mut self, leaf_key: &H256) -> Result<(), Error> {
+        self.leaves_map.remove(leaf_key);
+        Ok(())
+    }
+}
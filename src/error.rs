@@ -0,0 +1,60 @@
+//! Error type shared by the store, tree and proof modules.
+
+use core::fmt;
+
+/// Errors that can occur while mutating or querying a [`SparseMerkleTree`](crate::tree::SparseMerkleTree)
+/// or verifying a [`MerkleProof`](crate::merkle_proof::MerkleProof).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A branch node that the tree's current root says must exist is missing
+    /// from the store, at the given height.
+    MissingBranch(u8),
+    /// A leaf that the tree's current root says must exist is missing from
+    /// the store.
+    MissingLeaf,
+    /// A compiled proof's instruction stream could not be parsed.
+    CorruptedProof,
+    /// A compiled proof's stack underflowed or had leftover entries.
+    CorruptedStack,
+    /// A proof did not verify against the expected root.
+    InvalidProof,
+    /// The number of leaves supplied did not match what the proof expects.
+    IncorrectNumberOfLeaves { expected: usize, actual: usize },
+    /// Two keys passed together are not valid siblings (e.g. duplicated).
+    NonSiblingKeys,
+    /// The store or proof addressed a height outside of `0..TREE_HEIGHT`.
+    InvalidHeight(u8),
+    /// A store's underlying I/O (file, mmap) operation failed.
+    Io,
+    /// A store's open-addressing table had no empty or tombstone slot left
+    /// to place an entry in.
+    TableFull,
+    /// A leaf value exceeded the store's maximum representable size.
+    ValueTooLarge,
+    /// A store's on-disk layout (header, control bytes) was invalid.
+    CorruptedStore,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MissingBranch(height) => write!(f, "missing branch at height {}", height),
+            Error::MissingLeaf => write!(f, "missing leaf"),
+            Error::CorruptedProof => write!(f, "corrupted proof"),
+            Error::CorruptedStack => write!(f, "corrupted proof stack"),
+            Error::InvalidProof => write!(f, "invalid proof"),
+            Error::IncorrectNumberOfLeaves { expected, actual } => {
+                write!(f, "incorrect number of leaves: expected {}, got {}", expected, actual)
+            }
+            Error::NonSiblingKeys => write!(f, "keys are not siblings"),
+            Error::InvalidHeight(height) => write!(f, "invalid height {}", height),
+            Error::Io => write!(f, "store I/O error"),
+            Error::TableFull => write!(f, "store table is full"),
+            Error::ValueTooLarge => write!(f, "value exceeds the store's maximum size"),
+            Error::CorruptedStore => write!(f, "corrupted store layout"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
@@ -0,0 +1,184 @@
+//! Fixed-size 256-bit digest used for tree nodes, keys and values.
+
+use core::fmt;
+
+use crate::{traits::Value, vec::Vec};
+
+/// A 256-bit (32-byte) hash value.
+///
+/// This is the type every key, value digest and internal node hash in the
+/// tree is represented as once it has been fed through a [`Hasher`](crate::traits::Hasher).
+#[derive(Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct H256([u8; 32]);
+
+/// Alias kept for call sites that think of an `H256` as "a hash" rather than
+/// "a 256-bit word" -- the two are the same type.
+pub type Hash = H256;
+
+impl H256 {
+    /// The all-zero digest, used to represent an empty subtree.
+    pub const fn zero() -> Self {
+        H256([0u8; 32])
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0 == [0u8; 32]
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn as_slice_mut(&mut self) -> &mut [u8] {
+        &mut self.0
+    }
+
+    /// Reads the bit of the key that decides left/right branching at `height`,
+    /// counting from the leaf (`0`) up to the root (`255`).
+    pub fn get_bit(&self, height: u8) -> bool {
+        let byte_pos = 31 - (height >> 3) as usize;
+        let bit_pos = height & 7;
+        (self.0[byte_pos] >> bit_pos) & 1 != 0
+    }
+
+    pub fn set_bit(&mut self, height: u8) {
+        let byte_pos = 31 - (height >> 3) as usize;
+        let bit_pos = height & 7;
+        self.0[byte_pos] |= 1 << bit_pos;
+    }
+
+    pub fn clear_bit(&mut self, height: u8) {
+        let byte_pos = 31 - (height >> 3) as usize;
+        let bit_pos = height & 7;
+        self.0[byte_pos] &= !(1 << bit_pos);
+    }
+
+    /// Returns the highest height at which `self` and `other` take a different
+    /// branch, i.e. the height at which their paths fork.
+    pub fn fork_height(&self, other: &H256) -> u8 {
+        for height in (0..=255u8).rev() {
+            if self.get_bit(height) != other.get_bit(height) {
+                return height;
+            }
+        }
+        0
+    }
+}
+
+impl fmt::Debug for H256 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x")?;
+        for byte in self.0.iter() {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+impl From<[u8; 32]> for H256 {
+    fn from(v: [u8; 32]) -> Self {
+        H256(v)
+    }
+}
+
+impl From<H256> for [u8; 32] {
+    fn from(h: H256) -> Self {
+        h.0
+    }
+}
+
+impl AsRef<[u8]> for H256 {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Value for H256 {
+    fn zero() -> Self {
+        H256::zero()
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        H256::as_slice(self)
+    }
+}
+
+/// Lets `H256` back a [`PersistentStore`](crate::persistent_store::PersistentStore),
+/// whose `Store` impl round-trips values through `Vec<u8>`.
+impl TryFrom<Vec<u8>> for H256 {
+    type Error = ();
+
+    fn try_from(bytes: Vec<u8>) -> Result<Self, Self::Error> {
+        if bytes.len() != 32 {
+            return Err(());
+        }
+        let mut out = [0u8; 32];
+        out.copy_from_slice(&bytes);
+        Ok(H256(out))
+    }
+}
+
+/// Compares two byte slices for equality in constant time, so the branch
+/// taken by a verifier never depends on where (or whether) two digests
+/// differ.
+///
+/// Lengths are checked up front -- that comparison is not secret and is
+/// allowed to short-circuit -- but once both slices are the same length the
+/// full comparison always runs to completion regardless of earlier
+/// mismatches, using volatile reads/writes so the compiler cannot
+/// reintroduce an early exit.
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut r = 0u8;
+    for i in 0..a.len() {
+        // SAFETY: `i` is in bounds for both slices since their lengths were
+        // checked to be equal above.
+        unsafe {
+            let av = core::ptr::read_volatile(a.get_unchecked(i));
+            let bv = core::ptr::read_volatile(b.get_unchecked(i));
+            let r_cur = core::ptr::read_volatile(&r);
+            core::ptr::write_volatile(&mut r, r_cur | (av ^ bv));
+        }
+    }
+
+    unsafe {
+        let r_cur = core::ptr::read_volatile(&r);
+        core::ptr::write_volatile(&mut r, r_cur | (r_cur >> 4));
+        let r_cur = core::ptr::read_volatile(&r);
+        core::ptr::write_volatile(&mut r, r_cur | (r_cur >> 2));
+        let r_cur = core::ptr::read_volatile(&r);
+        core::ptr::write_volatile(&mut r, r_cur | (r_cur >> 1));
+        core::ptr::read_volatile(&r) & 1 == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{constant_time_eq, H256};
+
+    #[test]
+    fn get_set_clear_bit_roundtrip() {
+        let mut h = H256::zero();
+        assert!(!h.get_bit(10));
+        h.set_bit(10);
+        assert!(h.get_bit(10));
+        h.clear_bit(10);
+        assert!(!h.get_bit(10));
+    }
+
+    #[test]
+    fn fork_height_of_equal_hashes_is_zero() {
+        let a = H256::from([7u8; 32]);
+        assert_eq!(a.fork_height(&a), 0);
+    }
+
+    #[test]
+    fn constant_time_eq_matches_equality() {
+        assert!(constant_time_eq(&[1, 2, 3], &[1, 2, 3]));
+        assert!(!constant_time_eq(&[1, 2, 3], &[1, 2, 4]));
+        assert!(!constant_time_eq(&[1, 2, 3], &[1, 2, 3, 4]));
+    }
+}
@@ -18,6 +18,20 @@ impl Default for Blake2bHasher {
     }
 }
 
+impl Blake2bHasher {
+    /// Builds a hasher personalized with `personalization` instead of the
+    /// default `b"sparsemerkletree"` string, so different callers sharing a
+    /// key space never collide.
+    pub fn with_personalization(personalization: &[u8]) -> Self {
+        unsafe {
+            let mut hasher = Blake2bVar::new(BLAKE2B_DIGEST_BYTE_LEN).unwrap_unchecked();
+            hasher.update(personalization);
+
+            Blake2bHasher(hasher)
+        }
+    }
+}
+
 impl Hasher for Blake2bHasher {
     fn write_bytes(&mut self, bytes: &[u8]) {
         self.0.update(bytes);
@@ -31,4 +45,8 @@ impl Hasher for Blake2bHasher {
             digest.into()
         }
     }
+
+    fn new_keyed(key: &H256) -> Self {
+        Self::with_personalization(key.as_slice())
+    }
 }
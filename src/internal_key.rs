@@ -0,0 +1,34 @@
+//! Internal representation of a tree key, decoupled from the caller-facing [`Key`](crate::traits::Key) type.
+
+use crate::H256;
+
+/// The `H256` path a [`Key`](crate::traits::Key) maps to inside the tree.
+///
+/// The tree itself only ever walks and stores `H256` paths; `InternalKey`
+/// keeps that internal representation distinct from whatever type callers
+/// chose for `K`, so future key encodings (e.g. non-hash keys) only need a
+/// new `Into<H256>` impl rather than a change to the tree's storage layout.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct InternalKey(H256);
+
+impl InternalKey {
+    pub fn new(h256: H256) -> Self {
+        InternalKey(h256)
+    }
+
+    pub fn into_h256(self) -> H256 {
+        self.0
+    }
+}
+
+impl From<H256> for InternalKey {
+    fn from(h256: H256) -> Self {
+        InternalKey(h256)
+    }
+}
+
+impl From<InternalKey> for H256 {
+    fn from(key: InternalKey) -> Self {
+        key.0
+    }
+}
@@ -55,6 +55,8 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+#[cfg(feature = "blake3")]
+pub mod blake3_hasher;
 pub mod default_store;
 pub mod error;
 pub mod h256;
@@ -63,6 +65,8 @@ pub mod internal_blake2b;
 pub mod internal_key;
 pub mod merge;
 pub mod merkle_proof;
+#[cfg(feature = "mmap")]
+pub mod persistent_store;
 pub mod proof_ics23;
 pub mod sha256;
 #[cfg(test)]
@@ -85,15 +89,11 @@ pub const KEY_LIMIT: usize = 4_294_967_295u32 as usize;
 
 cfg_if::cfg_if! {
     if #[cfg(feature = "std")] {
-        use std::collections;
-        use std::vec;
-        use std::string;
-        use std::vec as vec_macro;
+        pub(crate) use std::collections;
+        pub(crate) use std::vec;
     } else {
         extern crate alloc;
-        use alloc::collections;
-        use alloc::vec;
-        use alloc::string;
-        use alloc::vec as vec_macro;
+        pub(crate) use alloc::collections;
+        pub(crate) use alloc::vec;
     }
 }
@@ -0,0 +1,90 @@
+//! Combines leaves and branch children into the hashes stored at each node.
+//!
+//! Leaves and internal nodes are hashed under distinct domain-separation
+//! tags -- `0x00` for leaves, `0x01` for internal nodes -- so that an
+//! internal node's preimage (`0x01 || left || right`) can never be
+//! reinterpreted as a valid leaf preimage (`0x00 || key || value_hash`).
+//! This closes the classic Merkle second-preimage attack where a proof for
+//! an internal node is passed off as a proof for a leaf (or vice versa).
+//!
+//! The tag is written first via [`Hasher::write_bytes`], exactly as in
+//! Solana's shred merkle tree layout.
+
+use crate::{
+    traits::{Hasher, Value},
+    H256,
+};
+
+/// Domain tag prefixed to a leaf's preimage: `H(0x00 || key || value_hash)`.
+pub const LEAF_DOMAIN_TAG: u8 = 0x00;
+/// Domain tag prefixed to an internal node's preimage: `H(0x01 || left || right)`.
+pub const INTERNAL_DOMAIN_TAG: u8 = 0x01;
+
+/// Builds the hasher every node hash in a tree is computed with: keyed to
+/// `hasher_key` if the tree was constructed with one, via
+/// [`Hasher::new_keyed`], or `H::default()` otherwise.
+fn new_hasher<H: Hasher + Default>(hasher_key: Option<&H256>) -> H {
+    match hasher_key {
+        Some(key) => H::new_keyed(key),
+        None => H::default(),
+    }
+}
+
+/// Hashes a value's byte representation down to an `H256` digest.
+///
+/// An all-zero value (the `Value::zero()` sentinel for "nothing stored
+/// here") hashes to `H256::zero()` rather than being fed through the
+/// hasher, so empty subtrees stay all-zero all the way up.
+pub fn hash_value<H: Hasher + Default, V: Value>(hasher_key: Option<&H256>, value: &V) -> H256 {
+    let bytes = value.as_slice();
+    if bytes.iter().all(|&b| b == 0) {
+        return H256::zero();
+    }
+    let mut hasher = new_hasher::<H>(hasher_key);
+    hasher.write_bytes(bytes);
+    hasher.finish()
+}
+
+/// Computes a leaf's node hash from its key and value digest.
+///
+/// A zeroed `value_hash` -- what [`hash_value`] returns for the
+/// `Value::zero()` sentinel, i.e. a deleted or never-written leaf -- collapses
+/// to `H256::zero()` rather than being hashed, so a deleted leaf is
+/// indistinguishable from one that was never written and empty subtrees stay
+/// all-zero all the way up.
+///
+/// With the `legacy-hashing` feature enabled this reverts to hashing
+/// `key || value_hash` without a domain tag, matching the root computed by
+/// versions of this crate prior to the second-preimage fix. Existing
+/// deployments that already committed to un-prefixed roots can enable the
+/// feature to keep verifying old proofs; new deployments should leave it
+/// off.
+pub fn hash_leaf<H: Hasher + Default>(hasher_key: Option<&H256>, key: &H256, value_hash: &H256) -> H256 {
+    if value_hash.is_zero() {
+        return H256::zero();
+    }
+    let mut hasher = new_hasher::<H>(hasher_key);
+    #[cfg(not(feature = "legacy-hashing"))]
+    hasher.write_byte(LEAF_DOMAIN_TAG);
+    hasher.write_h256(key);
+    hasher.write_h256(value_hash);
+    hasher.finish()
+}
+
+/// Merges two child hashes into their parent's node hash.
+///
+/// Two all-zero children merge to `H256::zero()` without touching the
+/// hasher, preserving the all-zero representation of empty subtrees.
+///
+/// See [`hash_leaf`] for the corresponding feature flag this shares.
+pub fn merge<H: Hasher + Default>(hasher_key: Option<&H256>, lhs: &H256, rhs: &H256) -> H256 {
+    if lhs.is_zero() && rhs.is_zero() {
+        return H256::zero();
+    }
+    let mut hasher = new_hasher::<H>(hasher_key);
+    #[cfg(not(feature = "legacy-hashing"))]
+    hasher.write_byte(INTERNAL_DOMAIN_TAG);
+    hasher.write_h256(lhs);
+    hasher.write_h256(rhs);
+    hasher.finish()
+}
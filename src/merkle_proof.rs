@@ -0,0 +1,140 @@
+//! Inclusion proofs for one or more leaves of a [`SparseMerkleTree`](crate::tree::SparseMerkleTree).
+
+use crate::{
+    error::Error,
+    h256::{constant_time_eq, H256},
+    merge,
+    traits::{Hasher, Key, Value},
+    vec::Vec,
+    TREE_HEIGHT,
+};
+
+/// An inclusion proof for a set of leaves, recorded as the sibling hashes
+/// needed to recompute the tree root from each leaf.
+///
+/// `paths[i]` holds the height-ordered (leaf height `0` up to the root,
+/// `TREE_HEIGHT - 1`) sibling hashes for the `i`-th leaf of the sorted key
+/// set the proof was generated for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MerkleProof {
+    paths: Vec<Vec<H256>>,
+}
+
+impl MerkleProof {
+    pub fn new(paths: Vec<Vec<H256>>) -> Self {
+        MerkleProof { paths }
+    }
+
+    /// Recomputes the root from `leaves` (sorted by key, matching the order
+    /// the proof was generated in) and compares it against `expected_root`
+    /// in constant time, so a verifier run against adversarial input never
+    /// leaks where (or whether) a digest mismatched.
+    ///
+    /// `hasher_key` must match the key (if any) the tree that produced
+    /// `expected_root` was constructed with -- see
+    /// [`SparseMerkleTree::with_hasher_key`](crate::tree::SparseMerkleTree::with_hasher_key).
+    pub fn verify<H: Hasher + Default, K: Key, V: Value, const N: usize>(
+        &self,
+        hasher_key: Option<&H256>,
+        expected_root: H256,
+        mut leaves: Vec<(K, V)>,
+    ) -> Result<bool, Error> {
+        if leaves.len() != self.paths.len() {
+            return Err(Error::IncorrectNumberOfLeaves {
+                expected: self.paths.len(),
+                actual: leaves.len(),
+            });
+        }
+        leaves.sort_by_key(|(k, _)| (*k).into());
+
+        for ((key, value), path) in leaves.into_iter().zip(self.paths.iter()) {
+            let key: H256 = key.into();
+            let root = compute_root::<H>(hasher_key, key, &value, path)?;
+            if !constant_time_eq(root.as_slice(), expected_root.as_slice()) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Serializes the proof into a compact, transport-friendly form.
+    pub fn compile(self) -> CompiledMerkleProof {
+        let mut buf = Vec::new();
+        buf.push(u8::try_from(self.paths.len()).unwrap_or(u8::MAX));
+        for path in &self.paths {
+            buf.push(u8::try_from(path.len()).unwrap_or(u8::MAX));
+            for sibling in path {
+                buf.extend_from_slice(sibling.as_slice());
+            }
+        }
+        CompiledMerkleProof(buf)
+    }
+}
+
+/// The wire-format encoding of a [`MerkleProof`], as produced by [`MerkleProof::compile`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompiledMerkleProof(pub Vec<u8>);
+
+impl CompiledMerkleProof {
+    pub fn verify<H: Hasher + Default, K: Key, V: Value, const N: usize>(
+        &self,
+        hasher_key: Option<&H256>,
+        expected_root: H256,
+        leaves: Vec<(K, V)>,
+    ) -> Result<bool, Error> {
+        let proof = self.decompile()?;
+        proof.verify::<H, K, V, N>(hasher_key, expected_root, leaves)
+    }
+
+    fn decompile(&self) -> Result<MerkleProof, Error> {
+        let bytes = &self.0;
+        let mut offset = 0usize;
+        let num_paths = *bytes.first().ok_or(Error::CorruptedProof)? as usize;
+        offset += 1;
+
+        let mut paths = Vec::with_capacity(num_paths);
+        for _ in 0..num_paths {
+            let path_len = *bytes.get(offset).ok_or(Error::CorruptedProof)? as usize;
+            offset += 1;
+
+            let mut path = Vec::with_capacity(path_len);
+            for _ in 0..path_len {
+                let chunk = bytes.get(offset..offset + 32).ok_or(Error::CorruptedProof)?;
+                let mut sibling = [0u8; 32];
+                sibling.copy_from_slice(chunk);
+                path.push(H256::from(sibling));
+                offset += 32;
+            }
+            paths.push(path);
+        }
+
+        Ok(MerkleProof::new(paths))
+    }
+}
+
+/// Recomputes the tree root for a single leaf, walking `path` from the leaf
+/// upward and merging in each sibling with [`merge::merge`]. Used by
+/// [`MerkleProof::verify`]; it hashes leaves and branches with the same
+/// [`merge`] functions as [`tree::SparseMerkleTree::update`](crate::tree::SparseMerkleTree::update),
+/// so both use the same domain-separated scheme.
+pub(crate) fn compute_root<H: Hasher + Default>(
+    hasher_key: Option<&H256>,
+    key: H256,
+    value: &impl Value,
+    path: &[H256],
+) -> Result<H256, Error> {
+    if path.len() != TREE_HEIGHT {
+        return Err(Error::CorruptedProof);
+    }
+
+    let value_hash = merge::hash_value::<H, _>(hasher_key, value);
+    let mut node = merge::hash_leaf::<H>(hasher_key, &key, &value_hash);
+    for (height, sibling) in (0..TREE_HEIGHT).map(|h| h as u8).zip(path.iter()) {
+        node = if key.get_bit(height) {
+            merge::merge::<H>(hasher_key, sibling, &node)
+        } else {
+            merge::merge::<H>(hasher_key, &node, sibling)
+        };
+    }
+    Ok(node)
+}
@@ -0,0 +1,680 @@
+//! An mmap-backed [`Store`] so a tree can be persisted to (and shared across
+//! processes via) a single file, instead of reserializing a pair of
+//! `BTreeMap`s on every load.
+//!
+//! The file is a fixed [`Header`] followed by two open-addressing tables --
+//! one for branch nodes, one for leaves -- each laid out SwissTable-style:
+//! a contiguous array of one control byte per slot (holding [`EMPTY_CTRL`],
+//! [`TOMBSTONE_CTRL`], or the low 7 bits of the key's hash) followed by the
+//! slot data itself. Probing inspects a group of [`GROUP_SIZE`] control
+//! bytes at a time -- via SSE2 `_mm_cmpeq_epi8`/`movemask` on `x86`/`x86_64`,
+//! or a scalar loop elsewhere -- before confirming a candidate by comparing
+//! the full key against the slot. This keeps lookups O(1) against the
+//! mapped file with no deserialization step.
+//!
+//! Only available behind the `mmap` feature so `no_std` builds are
+//! unaffected.
+
+use crate::{error::Error, traits::Store, H256};
+use memmap2::{MmapMut, MmapOptions};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+use std::vec::Vec;
+
+const MAGIC: [u8; 8] = *b"SMTSTOR1";
+const FORMAT_VERSION: u32 = 1;
+
+/// Control bytes are probed [`GROUP_SIZE`] at a time, matching the width of
+/// an SSE2 `__m128i` register.
+const GROUP_SIZE: usize = 16;
+/// Marks a slot that has never held an entry.
+const EMPTY_CTRL: u8 = 0xFF;
+/// Marks a slot whose entry was removed; probing must continue past it, but
+/// insertion may reuse it.
+const TOMBSTONE_CTRL: u8 = 0xFE;
+/// Grow the table once it is this full, matching SwissTable's default
+/// load-factor watermark.
+const MAX_LOAD_FACTOR_PERCENT: u8 = 87;
+
+/// Leaf values wider than this are rejected -- the table stores fixed-width
+/// slots so lookups stay O(1) against the raw mmap.
+const MAX_VALUE_BYTES: usize = 256;
+
+const HEADER_BYTES: usize = 8 + 4 + 8 + 8 + 8 + 1;
+const BRANCH_SLOT_BYTES: usize = 32 + 32 + 32; // key, left, right
+const LEAF_SLOT_BYTES: usize = 32 + 8 + MAX_VALUE_BYTES; // key, value len, value
+
+/// On-disk header, written once at file creation and refreshed on grow.
+struct Header {
+    version: u32,
+    branch_capacity: u64,
+    leaf_capacity: u64,
+    load_factor_watermark: u8,
+}
+
+impl Header {
+    fn read(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < HEADER_BYTES || bytes[0..8] != MAGIC {
+            return Err(Error::CorruptedStore);
+        }
+        let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+        let branch_capacity = u64::from_le_bytes(bytes[12..20].try_into().unwrap());
+        let leaf_capacity = u64::from_le_bytes(bytes[20..28].try_into().unwrap());
+        let load_factor_watermark = bytes[28];
+        Ok(Header { version, branch_capacity, leaf_capacity, load_factor_watermark })
+    }
+
+    fn write(&self, bytes: &mut [u8]) {
+        bytes[0..8].copy_from_slice(&MAGIC);
+        bytes[8..12].copy_from_slice(&self.version.to_le_bytes());
+        bytes[12..20].copy_from_slice(&self.branch_capacity.to_le_bytes());
+        bytes[20..28].copy_from_slice(&self.leaf_capacity.to_le_bytes());
+        bytes[28] = self.load_factor_watermark;
+    }
+}
+
+/// mmap-backed, SwissTable-probed [`Store`] implementation.
+///
+/// `N` mirrors the tree height in bytes, kept for parity with [`DefaultStore`](crate::default_store::DefaultStore).
+pub struct PersistentStore<const N: usize> {
+    file: File,
+    mmap: MmapMut,
+    branch_capacity: u64,
+    leaf_capacity: u64,
+    branch_len: u64,
+    leaf_len: u64,
+}
+
+impl<const N: usize> PersistentStore<N> {
+    /// Opens (creating if necessary) a store file with room for
+    /// `initial_capacity` branch slots and the same number of leaf slots.
+    ///
+    /// If the file already holds a valid header, its recorded capacities
+    /// are used instead of `initial_capacity` (so a previously grown store
+    /// is never truncated back down), and `branch_len`/`leaf_len` are
+    /// recovered by scanning the control bytes rather than assumed to be
+    /// zero.
+    pub fn open<P: AsRef<Path>>(path: P, initial_capacity: u64) -> io::Result<Self> {
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+
+        let existing_len = file.metadata()?.len();
+        let existing_header = if existing_len >= HEADER_BYTES as u64 {
+            let mut header_bytes = [0u8; HEADER_BYTES];
+            file.seek(SeekFrom::Start(0))?;
+            file.read_exact(&mut header_bytes)?;
+            Header::read(&header_bytes).ok().filter(|header| header.version == FORMAT_VERSION)
+        } else {
+            None
+        };
+
+        let (branch_capacity, leaf_capacity) = match &existing_header {
+            Some(header) => (header.branch_capacity, header.leaf_capacity),
+            None => {
+                let capacity = initial_capacity.next_power_of_two().max(GROUP_SIZE as u64);
+                (capacity, capacity)
+            }
+        };
+
+        let file_len = Self::layout_len(branch_capacity, leaf_capacity);
+        file.set_len(file_len)?;
+        let mut mmap = unsafe { MmapOptions::new().len(file_len as usize).map_mut(&file)? };
+
+        if existing_header.is_none() {
+            let header = Header {
+                version: FORMAT_VERSION,
+                branch_capacity,
+                leaf_capacity,
+                load_factor_watermark: MAX_LOAD_FACTOR_PERCENT,
+            };
+            header.write(&mut mmap);
+            Self::init_controls(&mut mmap, branch_capacity, leaf_capacity);
+        }
+
+        let (branch_len, leaf_len) = if existing_header.is_some() {
+            let (branch_ctrl_start, branch_ctrl_end, leaf_ctrl_start, leaf_ctrl_end) =
+                Self::ctrl_offsets(branch_capacity, leaf_capacity);
+            (
+                count_live_ctrl(&mmap[branch_ctrl_start..branch_ctrl_end]),
+                count_live_ctrl(&mmap[leaf_ctrl_start..leaf_ctrl_end]),
+            )
+        } else {
+            (0, 0)
+        };
+
+        Ok(PersistentStore {
+            file,
+            mmap,
+            branch_capacity,
+            leaf_capacity,
+            branch_len,
+            leaf_len,
+        })
+    }
+
+    fn layout_len(branch_capacity: u64, leaf_capacity: u64) -> u64 {
+        HEADER_BYTES as u64
+            + branch_capacity * (1 + BRANCH_SLOT_BYTES as u64)
+            + leaf_capacity * (1 + LEAF_SLOT_BYTES as u64)
+    }
+
+    /// The `(branch_ctrl_start, branch_ctrl_end, leaf_ctrl_start, leaf_ctrl_end)`
+    /// byte offsets for a table of the given capacities, shared by
+    /// [`init_controls`](Self::init_controls) and [`open`](Self::open) (which
+    /// needs them before a `PersistentStore` -- and so `self` -- exists).
+    fn ctrl_offsets(branch_capacity: u64, leaf_capacity: u64) -> (usize, usize, usize, usize) {
+        let branch_ctrl_start = HEADER_BYTES;
+        let branch_ctrl_end = branch_ctrl_start + branch_capacity as usize;
+        let leaf_ctrl_start = branch_ctrl_end + branch_capacity as usize * BRANCH_SLOT_BYTES;
+        let leaf_ctrl_end = leaf_ctrl_start + leaf_capacity as usize;
+        (branch_ctrl_start, branch_ctrl_end, leaf_ctrl_start, leaf_ctrl_end)
+    }
+
+    fn init_controls(mmap: &mut MmapMut, branch_capacity: u64, leaf_capacity: u64) {
+        let (branch_ctrl_start, branch_ctrl_end, leaf_ctrl_start, leaf_ctrl_end) =
+            Self::ctrl_offsets(branch_capacity, leaf_capacity);
+        mmap[branch_ctrl_start..branch_ctrl_end].fill(EMPTY_CTRL);
+        mmap[leaf_ctrl_start..leaf_ctrl_end].fill(EMPTY_CTRL);
+    }
+
+    fn branch_ctrl_offset(&self) -> usize {
+        HEADER_BYTES
+    }
+
+    fn branch_slots_offset(&self) -> usize {
+        self.branch_ctrl_offset() + self.branch_capacity as usize
+    }
+
+    fn leaf_ctrl_offset(&self) -> usize {
+        self.branch_slots_offset() + self.branch_capacity as usize * BRANCH_SLOT_BYTES
+    }
+
+    fn leaf_slots_offset(&self) -> usize {
+        self.leaf_ctrl_offset() + self.leaf_capacity as usize
+    }
+
+    /// Flushes pending writes to disk. Call after a batch of updates, not
+    /// per-insert, to amortize the `msync` cost.
+    pub fn flush(&self) -> io::Result<()> {
+        self.mmap.flush()
+    }
+
+    /// Probes `key` against a table, returning the slot index holding it (a
+    /// hit) or the first usable slot for it (a tombstone-or-empty slot, on a
+    /// miss). Each group of [`GROUP_SIZE`] control bytes is matched against
+    /// `key_h2` in one [`group_match_mask`] call; `slot_matches` confirms a
+    /// candidate by comparing the full key, since the 7-bit tag alone can
+    /// collide.
+    fn probe(ctrl: &[u8], capacity: u64, key_h2: u8, mut slot_matches: impl FnMut(usize) -> bool) -> ProbeResult {
+        let capacity = capacity as usize;
+        let group_count = (capacity / GROUP_SIZE).max(1);
+        let mut first_tombstone = None;
+
+        // Triangular probing: the stride between successive groups grows by
+        // one group each step, so probes spread out across the table instead
+        // of clustering behind a popular starting slot.
+        let mut group_index = (key_h2 as usize).wrapping_mul(0x9E37_79B1) % group_count;
+        let mut stride = 1usize;
+
+        loop {
+            let group_start = (group_index * GROUP_SIZE) % capacity;
+            let group = &ctrl[group_start..group_start + GROUP_SIZE.min(capacity - group_start)];
+
+            let match_mask = group_match_mask(group, key_h2);
+            for offset in 0..group.len() {
+                if match_mask & (1 << offset) != 0 {
+                    let slot = group_start + offset;
+                    if slot_matches(slot) {
+                        return ProbeResult::Found(slot);
+                    }
+                }
+            }
+
+            let empty_mask = group_match_mask(group, EMPTY_CTRL);
+            if empty_mask != 0 {
+                let offset = empty_mask.trailing_zeros() as usize;
+                return ProbeResult::Insert(first_tombstone.unwrap_or(group_start + offset));
+            }
+
+            if first_tombstone.is_none() {
+                let tombstone_mask = group_match_mask(group, TOMBSTONE_CTRL);
+                if tombstone_mask != 0 {
+                    first_tombstone = Some(group_start + tombstone_mask.trailing_zeros() as usize);
+                }
+            }
+
+            group_index = (group_index + stride) % group_count;
+            stride += 1;
+            if stride * GROUP_SIZE > capacity * 2 {
+                // Table is full of tombstones/entries with no empty slot found;
+                // fall back to whatever tombstone we saw, or signal "full".
+                return match first_tombstone {
+                    Some(slot) => ProbeResult::Insert(slot),
+                    None => ProbeResult::Full,
+                };
+            }
+        }
+    }
+}
+
+enum ProbeResult {
+    Found(usize),
+    Insert(usize),
+    Full,
+}
+
+/// Finds candidate slot offsets within one group of control bytes whose
+/// control byte equals `h2`. On `x86`/`x86_64` this is a single SSE2
+/// compare-and-movemask; everywhere else it is a scalar byte scan.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+fn group_match_mask(group: &[u8], h2: u8) -> u16 {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::*;
+
+    if !is_x86_feature_detected!("sse2") || group.len() < GROUP_SIZE {
+        return group_match_mask_scalar(group, h2);
+    }
+
+    unsafe {
+        let mut buf = [0u8; GROUP_SIZE];
+        buf[..group.len()].copy_from_slice(group);
+        let haystack = _mm_loadu_si128(buf.as_ptr() as *const __m128i);
+        let needle = _mm_set1_epi8(h2 as i8);
+        let eq = _mm_cmpeq_epi8(haystack, needle);
+        _mm_movemask_epi8(eq) as u16
+    }
+}
+
+#[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
+fn group_match_mask(group: &[u8], h2: u8) -> u16 {
+    group_match_mask_scalar(group, h2)
+}
+
+fn group_match_mask_scalar(group: &[u8], h2: u8) -> u16 {
+    let mut mask = 0u16;
+    for (i, &byte) in group.iter().enumerate() {
+        if byte == h2 {
+            mask |= 1 << i;
+        }
+    }
+    mask
+}
+
+/// Derives the 7-bit control tag from a key's hash (the low 7 bits of the
+/// first byte), reserving `0xFE`/`0xFF` for tombstone/empty.
+fn key_h2(key: &H256) -> u8 {
+    key.as_slice()[0] & 0x7F
+}
+
+/// Counts slots holding a live entry (neither [`EMPTY_CTRL`] nor
+/// [`TOMBSTONE_CTRL`]), used to recover `branch_len`/`leaf_len` when opening
+/// a file that already has entries in it.
+fn count_live_ctrl(ctrl: &[u8]) -> u64 {
+    ctrl.iter().filter(|&&b| b != EMPTY_CTRL && b != TOMBSTONE_CTRL).count() as u64
+}
+
+impl<V: Clone, const N: usize> Store<V, N> for PersistentStore<N>
+where
+    V: AsRef<[u8]> + TryFrom<Vec<u8>>,
+{
+    fn get_branch(&self, node: &H256) -> Result<Option<(H256, H256)>, Error> {
+        let ctrl_start = self.branch_ctrl_offset();
+        let ctrl = &self.mmap[ctrl_start..ctrl_start + self.branch_capacity as usize];
+        let slots_start = self.branch_slots_offset();
+        let key_bytes = node.as_slice();
+
+        match PersistentStore::<N>::probe(ctrl, self.branch_capacity, key_h2(node), |slot| {
+            let slot_start = slots_start + slot * BRANCH_SLOT_BYTES;
+            &self.mmap[slot_start..slot_start + 32] == key_bytes
+        }) {
+            ProbeResult::Found(slot) => {
+                let slot_start = slots_start + slot * BRANCH_SLOT_BYTES;
+                let mut left = [0u8; 32];
+                let mut right = [0u8; 32];
+                left.copy_from_slice(&self.mmap[slot_start + 32..slot_start + 64]);
+                right.copy_from_slice(&self.mmap[slot_start + 64..slot_start + 96]);
+                Ok(Some((left.into(), right.into())))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn get_leaf(&self, leaf_key: &H256) -> Result<Option<V>, Error> {
+        let ctrl_start = self.leaf_ctrl_offset();
+        let ctrl = &self.mmap[ctrl_start..ctrl_start + self.leaf_capacity as usize];
+        let slots_start = self.leaf_slots_offset();
+        let key_bytes = leaf_key.as_slice();
+
+        match PersistentStore::<N>::probe(ctrl, self.leaf_capacity, key_h2(leaf_key), |slot| {
+            let slot_start = slots_start + slot * LEAF_SLOT_BYTES;
+            &self.mmap[slot_start..slot_start + 32] == key_bytes
+        }) {
+            ProbeResult::Found(slot) => {
+                let slot_start = slots_start + slot * LEAF_SLOT_BYTES;
+                let len = u64::from_le_bytes(self.mmap[slot_start + 32..slot_start + 40].try_into().unwrap()) as usize;
+                let value_bytes = self.mmap[slot_start + 40..slot_start + 40 + len].to_vec();
+                V::try_from(value_bytes).map(Some).map_err(|_| Error::CorruptedStore)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn insert_branch(&mut self, node: H256, left: H256, right: H256) -> Result<(), Error> {
+        self.maybe_grow_branches()?;
+        self.insert_branch_raw(node, left, right)
+    }
+
+    fn insert_leaf(&mut self, leaf_key: H256, value: V) -> Result<(), Error> {
+        let bytes = value.as_ref().to_vec();
+        if bytes.len() > MAX_VALUE_BYTES {
+            return Err(Error::ValueTooLarge);
+        }
+        self.maybe_grow_leaves()?;
+        self.insert_leaf_raw(leaf_key, bytes)
+    }
+
+    fn remove_branch(&mut self, node: &H256) -> Result<(), Error> {
+        let ctrl_start = self.branch_ctrl_offset();
+        let slots_start = self.branch_slots_offset();
+        let capacity = self.branch_capacity;
+        let key_bytes = node.as_slice();
+
+        let probe = {
+            let ctrl = &self.mmap[ctrl_start..ctrl_start + capacity as usize];
+            PersistentStore::<N>::probe(ctrl, capacity, key_h2(node), |slot| {
+                let slot_start = slots_start + slot * BRANCH_SLOT_BYTES;
+                &self.mmap[slot_start..slot_start + 32] == key_bytes
+            })
+        };
+        if let ProbeResult::Found(slot) = probe {
+            self.mmap[ctrl_start + slot] = TOMBSTONE_CTRL;
+            self.branch_len -= 1;
+        }
+        Ok(())
+    }
+
+    fn remove_leaf(&mut self, leaf_key: &H256) -> Result<(), Error> {
+        let ctrl_start = self.leaf_ctrl_offset();
+        let slots_start = self.leaf_slots_offset();
+        let capacity = self.leaf_capacity;
+        let key_bytes = leaf_key.as_slice();
+
+        let probe = {
+            let ctrl = &self.mmap[ctrl_start..ctrl_start + capacity as usize];
+            PersistentStore::<N>::probe(ctrl, capacity, key_h2(leaf_key), |slot| {
+                let slot_start = slots_start + slot * LEAF_SLOT_BYTES;
+                &self.mmap[slot_start..slot_start + 32] == key_bytes
+            })
+        };
+        if let ProbeResult::Found(slot) = probe {
+            self.mmap[ctrl_start + slot] = TOMBSTONE_CTRL;
+            self.leaf_len -= 1;
+        }
+        Ok(())
+    }
+}
+
+impl<const N: usize> PersistentStore<N> {
+    fn maybe_grow_branches(&mut self) -> Result<(), Error> {
+        if self.branch_len * 100 < self.branch_capacity * MAX_LOAD_FACTOR_PERCENT as u64 {
+            return Ok(());
+        }
+        self.rehash_grow(self.branch_capacity * 2, self.leaf_capacity)
+    }
+
+    fn maybe_grow_leaves(&mut self) -> Result<(), Error> {
+        if self.leaf_len * 100 < self.leaf_capacity * MAX_LOAD_FACTOR_PERCENT as u64 {
+            return Ok(());
+        }
+        self.rehash_grow(self.branch_capacity, self.leaf_capacity * 2)
+    }
+
+    /// Grows one or both tables in place: snapshots every live entry from
+    /// the current mmap, resizes the file, re-initializes the control
+    /// arrays, then re-inserts every entry so it lands in its new probe
+    /// sequence. This is the only point at which the table's capacity
+    /// changes; everyday inserts/removes only ever touch existing slots.
+    fn rehash_grow(&mut self, new_branch_capacity: u64, new_leaf_capacity: u64) -> Result<(), Error> {
+        let mut branches = Vec::new();
+        {
+            let ctrl_start = self.branch_ctrl_offset();
+            let slots_start = self.branch_slots_offset();
+            for slot in 0..self.branch_capacity as usize {
+                let ctrl = self.mmap[ctrl_start + slot];
+                if ctrl == EMPTY_CTRL || ctrl == TOMBSTONE_CTRL {
+                    continue;
+                }
+                let slot_start = slots_start + slot * BRANCH_SLOT_BYTES;
+                let mut key = [0u8; 32];
+                let mut left = [0u8; 32];
+                let mut right = [0u8; 32];
+                key.copy_from_slice(&self.mmap[slot_start..slot_start + 32]);
+                left.copy_from_slice(&self.mmap[slot_start + 32..slot_start + 64]);
+                right.copy_from_slice(&self.mmap[slot_start + 64..slot_start + 96]);
+                branches.push((H256::from(key), H256::from(left), H256::from(right)));
+            }
+        }
+
+        let mut leaves = Vec::new();
+        {
+            let ctrl_start = self.leaf_ctrl_offset();
+            let slots_start = self.leaf_slots_offset();
+            for slot in 0..self.leaf_capacity as usize {
+                let ctrl = self.mmap[ctrl_start + slot];
+                if ctrl == EMPTY_CTRL || ctrl == TOMBSTONE_CTRL {
+                    continue;
+                }
+                let slot_start = slots_start + slot * LEAF_SLOT_BYTES;
+                let mut key = [0u8; 32];
+                key.copy_from_slice(&self.mmap[slot_start..slot_start + 32]);
+                let len = u64::from_le_bytes(self.mmap[slot_start + 32..slot_start + 40].try_into().unwrap()) as usize;
+                let value_bytes = self.mmap[slot_start + 40..slot_start + 40 + len].to_vec();
+                leaves.push((H256::from(key), value_bytes));
+            }
+        }
+
+        let file_len = Self::layout_len(new_branch_capacity, new_leaf_capacity);
+        self.file.set_len(file_len).map_err(|_| Error::Io)?;
+        self.mmap =
+            unsafe { MmapOptions::new().len(file_len as usize).map_mut(&self.file).map_err(|_| Error::Io)? };
+        self.branch_capacity = new_branch_capacity;
+        self.leaf_capacity = new_leaf_capacity;
+        self.branch_len = 0;
+        self.leaf_len = 0;
+
+        let header = Header {
+            version: FORMAT_VERSION,
+            branch_capacity: new_branch_capacity,
+            leaf_capacity: new_leaf_capacity,
+            load_factor_watermark: MAX_LOAD_FACTOR_PERCENT,
+        };
+        header.write(&mut self.mmap);
+        Self::init_controls(&mut self.mmap, new_branch_capacity, new_leaf_capacity);
+
+        for (node, left, right) in branches {
+            self.insert_branch_raw(node, left, right)?;
+        }
+        for (key, value_bytes) in leaves {
+            self.insert_leaf_raw(key, value_bytes)?;
+        }
+
+        Ok(())
+    }
+
+    /// Probes for `node`'s slot and writes its children, without checking
+    /// (or tripping) the grow watermark -- used both by the public
+    /// `insert_branch` (after it has already grown if needed) and by
+    /// [`rehash_grow`](Self::rehash_grow) re-inserting into a freshly sized table.
+    fn insert_branch_raw(&mut self, node: H256, left: H256, right: H256) -> Result<(), Error> {
+        let ctrl_start = self.branch_ctrl_offset();
+        let slots_start = self.branch_slots_offset();
+        let capacity = self.branch_capacity;
+        let h2 = key_h2(&node);
+        let key_bytes = node.as_slice();
+
+        let probe = {
+            let ctrl = &self.mmap[ctrl_start..ctrl_start + capacity as usize];
+            PersistentStore::<N>::probe(ctrl, capacity, h2, |slot| {
+                let slot_start = slots_start + slot * BRANCH_SLOT_BYTES;
+                &self.mmap[slot_start..slot_start + 32] == key_bytes
+            })
+        };
+
+        let (slot, is_new) = match probe {
+            ProbeResult::Found(slot) => (slot, false),
+            ProbeResult::Insert(slot) => (slot, true),
+            ProbeResult::Full => return Err(Error::TableFull),
+        };
+
+        self.mmap[ctrl_start + slot] = h2;
+        let slot_start = slots_start + slot * BRANCH_SLOT_BYTES;
+        self.mmap[slot_start..slot_start + 32].copy_from_slice(node.as_slice());
+        self.mmap[slot_start + 32..slot_start + 64].copy_from_slice(left.as_slice());
+        self.mmap[slot_start + 64..slot_start + 96].copy_from_slice(right.as_slice());
+        if is_new {
+            self.branch_len += 1;
+        }
+        Ok(())
+    }
+
+    /// Raw counterpart of [`insert_branch_raw`](Self::insert_branch_raw) for leaves.
+    fn insert_leaf_raw(&mut self, leaf_key: H256, bytes: Vec<u8>) -> Result<(), Error> {
+        let ctrl_start = self.leaf_ctrl_offset();
+        let slots_start = self.leaf_slots_offset();
+        let capacity = self.leaf_capacity;
+        let h2 = key_h2(&leaf_key);
+        let key_bytes = leaf_key.as_slice();
+
+        let probe = {
+            let ctrl = &self.mmap[ctrl_start..ctrl_start + capacity as usize];
+            PersistentStore::<N>::probe(ctrl, capacity, h2, |slot| {
+                let slot_start = slots_start + slot * LEAF_SLOT_BYTES;
+                &self.mmap[slot_start..slot_start + 32] == key_bytes
+            })
+        };
+
+        let (slot, is_new) = match probe {
+            ProbeResult::Found(slot) => (slot, false),
+            ProbeResult::Insert(slot) => (slot, true),
+            ProbeResult::Full => return Err(Error::TableFull),
+        };
+
+        self.mmap[ctrl_start + slot] = h2;
+        let slot_start = slots_start + slot * LEAF_SLOT_BYTES;
+        self.mmap[slot_start..slot_start + 32].copy_from_slice(leaf_key.as_slice());
+        self.mmap[slot_start + 32..slot_start + 40].copy_from_slice(&(bytes.len() as u64).to_le_bytes());
+        self.mmap[slot_start + 40..slot_start + 40 + bytes.len()].copy_from_slice(&bytes);
+        if is_new {
+            self.leaf_len += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PersistentStore;
+    use crate::{traits::Store, H256};
+    use std::path::PathBuf;
+
+    /// Each test gets its own file under the system temp dir, named after
+    /// the test and the process id so parallel `cargo test` runs never
+    /// collide.
+    fn temp_path(name: &str) -> PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push(format!("smt-persistent-store-test-{}-{}", name, std::process::id()));
+        path
+    }
+
+    fn open_fresh(name: &str) -> PersistentStore<32> {
+        let path = temp_path(name);
+        let _ = std::fs::remove_file(&path);
+        PersistentStore::open(path, 16).expect("open")
+    }
+
+    #[test]
+    fn insert_then_get_branch_round_trips() {
+        let mut store = open_fresh("branch-round-trip");
+        let node = H256::from([1u8; 32]);
+        let left = H256::from([2u8; 32]);
+        let right = H256::from([3u8; 32]);
+
+        Store::<H256, 32>::insert_branch(&mut store, node, left, right).expect("insert_branch");
+
+        assert_eq!(Store::<H256, 32>::get_branch(&store, &node).expect("get_branch"), Some((left, right)));
+    }
+
+    #[test]
+    fn insert_then_get_leaf_round_trips() {
+        let mut store = open_fresh("leaf-round-trip");
+        let key = H256::from([4u8; 32]);
+        let value = H256::from([5u8; 32]);
+
+        Store::<H256, 32>::insert_leaf(&mut store, key, value).expect("insert_leaf");
+
+        assert_eq!(Store::<H256, 32>::get_leaf(&store, &key).expect("get_leaf"), Some(value));
+    }
+
+    #[test]
+    fn overwriting_a_branch_does_not_inflate_len() {
+        let mut store = open_fresh("no-double-count");
+        let node = H256::from([8u8; 32]);
+
+        Store::<H256, 32>::insert_branch(&mut store, node, H256::from([1u8; 32]), H256::from([2u8; 32]))
+            .expect("insert");
+        Store::<H256, 32>::insert_branch(&mut store, node, H256::from([3u8; 32]), H256::from([4u8; 32]))
+            .expect("overwrite");
+
+        assert_eq!(store.branch_len, 1);
+    }
+
+    #[test]
+    fn insert_past_load_factor_triggers_grow_and_rehash() {
+        let mut store = open_fresh("grow-and-rehash");
+        assert_eq!(store.branch_capacity, 16);
+
+        // 16 slots at an 87% watermark grow on the 15th insert; go well past
+        // that so at least one grow (and its rehash of every prior entry)
+        // has definitely happened.
+        let nodes: Vec<H256> = (0u8..40)
+            .map(|i| {
+                let mut key = [0u8; 32];
+                key[0] = i;
+                H256::from(key)
+            })
+            .collect();
+        for (i, &node) in nodes.iter().enumerate() {
+            let left = H256::from([i as u8; 32]);
+            let right = H256::from([i as u8 + 1; 32]);
+            Store::<H256, 32>::insert_branch(&mut store, node, left, right).expect("insert_branch");
+        }
+
+        assert!(store.branch_capacity > 16);
+        assert_eq!(store.branch_len, nodes.len() as u64);
+        for (i, &node) in nodes.iter().enumerate() {
+            let left = H256::from([i as u8; 32]);
+            let right = H256::from([i as u8 + 1; 32]);
+            assert_eq!(Store::<H256, 32>::get_branch(&store, &node).expect("get_branch"), Some((left, right)));
+        }
+    }
+
+    #[test]
+    fn reopen_preserves_entries_and_len() {
+        let path = temp_path("reopen");
+        let _ = std::fs::remove_file(&path);
+        let key = H256::from([6u8; 32]);
+        let value = H256::from([7u8; 32]);
+
+        {
+            let mut store: PersistentStore<32> = PersistentStore::open(&path, 16).expect("open");
+            Store::<H256, 32>::insert_leaf(&mut store, key, value).expect("insert_leaf");
+            store.flush().expect("flush");
+        }
+
+        let store: PersistentStore<32> = PersistentStore::open(&path, 16).expect("reopen");
+        assert_eq!(Store::<H256, 32>::get_leaf(&store, &key).expect("get_leaf"), Some(value));
+        assert_eq!(store.leaf_len, 1);
+    }
+}
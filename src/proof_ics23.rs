@@ -0,0 +1,29 @@
+//! Conversion from an existence proof shaped after
+//! [ICS23](https://github.com/cosmos/ibc/tree/main/spec/core/ics-023-vector-commitments)
+//! into this crate's [`CompiledMerkleProof`](crate::merkle_proof::CompiledMerkleProof).
+//! Only that direction is provided: the sibling path is carried over, the
+//! proof's key and value are not, and nothing here encodes a proof in the
+//! ICS23 wire format.
+
+use crate::{merkle_proof::CompiledMerkleProof, H256};
+
+/// An ICS23-style existence proof: the leaf key/value and the sibling path
+/// up to the root, in the generic shape ICS23 clients expect.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ics23ExistenceProof {
+    pub key: H256,
+    pub value: H256,
+    pub path: crate::vec::Vec<H256>,
+}
+
+impl From<Ics23ExistenceProof> for CompiledMerkleProof {
+    fn from(proof: Ics23ExistenceProof) -> Self {
+        let mut buf = crate::vec::Vec::new();
+        buf.push(1u8);
+        buf.push(u8::try_from(proof.path.len()).unwrap_or(u8::MAX));
+        for sibling in &proof.path {
+            buf.extend_from_slice(sibling.as_slice());
+        }
+        CompiledMerkleProof(buf)
+    }
+}
@@ -0,0 +1,26 @@
+//! SHA-256 backed [`Hasher`](crate::traits::Hasher) implementation.
+
+use crate::{traits::Hasher, H256};
+use sha2::{Digest, Sha256};
+
+#[derive(Default)]
+pub struct Sha256Hasher(Sha256);
+
+impl Hasher for Sha256Hasher {
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.0.update(bytes);
+    }
+
+    fn finish(self) -> H256 {
+        let digest = self.0.finalize();
+        let mut out = [0u8; 32];
+        out.copy_from_slice(&digest);
+        out.into()
+    }
+
+    fn new_keyed(key: &H256) -> Self {
+        let mut hasher = Self::default();
+        hasher.write_h256(key);
+        hasher
+    }
+}
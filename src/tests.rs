@@ -0,0 +1,186 @@
+use crate::{default_store::DefaultStore, sha256::Sha256Hasher, tree::SparseMerkleTree, H256};
+
+type SMT = SparseMerkleTree<Sha256Hasher, H256, H256, DefaultStore<H256, 32>, 32>;
+
+#[test]
+fn update_then_get_round_trips() {
+    let mut tree = SMT::default();
+    let key = H256::from([1u8; 32]);
+    let value = H256::from([2u8; 32]);
+
+    tree.update(key, value).expect("update");
+
+    assert_eq!(tree.get(&key).expect("get"), value);
+    assert!(!tree.root().is_zero());
+}
+
+#[test]
+fn missing_key_reads_as_zero() {
+    let tree = SMT::default();
+    let key = H256::from([3u8; 32]);
+
+    assert_eq!(tree.get(&key).expect("get"), H256::zero());
+}
+
+#[test]
+fn merkle_proof_verifies_against_root() {
+    let mut tree = SMT::default();
+    let key = H256::from([4u8; 32]);
+    let value = H256::from([5u8; 32]);
+    tree.update(key, value).expect("update");
+
+    let proof = tree.merkle_proof(vec![key]).expect("proof");
+    let valid = proof
+        .verify::<Sha256Hasher, H256, H256, 32>(None, *tree.root(), vec![(key, value)])
+        .expect("verify");
+
+    assert!(valid);
+}
+
+#[test]
+fn update_all_matches_sequential_updates() {
+    let changes: Vec<(H256, H256)> = (0u8..16)
+        .map(|i| {
+            let mut key = [0u8; 32];
+            key[30] = i;
+            key[31] = i.wrapping_mul(7);
+            (H256::from(key), H256::from([i; 32]))
+        })
+        .collect();
+
+    let mut sequential = SMT::default();
+    for (key, value) in changes.clone() {
+        sequential.update(key, value).expect("update");
+    }
+
+    let mut batched = SMT::default();
+    batched.update_all(changes).expect("update_all");
+
+    assert_eq!(sequential.root(), batched.root());
+}
+
+#[test]
+fn update_all_with_no_changes_preserves_root() {
+    let mut tree = SMT::default();
+    tree.update(H256::from([1u8; 32]), H256::from([2u8; 32])).expect("update");
+    let root_before = *tree.root();
+
+    tree.update_all(vec![]).expect("update_all");
+
+    assert_eq!(*tree.root(), root_before);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_update_all_with_no_changes_preserves_root() {
+    let mut tree = SMT::default();
+    tree.update(H256::from([1u8; 32]), H256::from([2u8; 32])).expect("update");
+    let root_before = *tree.root();
+
+    tree.par_update_all(vec![]).expect("par_update_all");
+
+    assert_eq!(*tree.root(), root_before);
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_update_all_matches_sequential_updates() {
+    let changes: Vec<(H256, H256)> = (0u8..64)
+        .map(|i| {
+            let mut key = [0u8; 32];
+            key[0] = i.wrapping_mul(3);
+            key[31] = i;
+            (H256::from(key), H256::from([i; 32]))
+        })
+        .collect();
+
+    let mut sequential = SMT::default();
+    for (key, value) in changes.clone() {
+        sequential.update(key, value).expect("update");
+    }
+
+    let mut parallel = SMT::default();
+    parallel.par_update_all(changes).expect("par_update_all");
+
+    assert_eq!(sequential.root(), parallel.root());
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_merkle_proof_matches_merkle_proof() {
+    let mut tree = SMT::default();
+    let keys: Vec<H256> = (0u8..8)
+        .map(|i| {
+            let mut key = [0u8; 32];
+            key[31] = i;
+            H256::from(key)
+        })
+        .collect();
+    for &key in &keys {
+        tree.update(key, H256::from([7u8; 32])).expect("update");
+    }
+
+    let sequential = tree.merkle_proof(keys.clone()).expect("merkle_proof");
+    let parallel = tree.par_merkle_proof(keys).expect("par_merkle_proof");
+
+    assert_eq!(sequential, parallel);
+}
+
+#[test]
+fn sha256_hasher_key_changes_root() {
+    let key = H256::from([1u8; 32]);
+    let value = H256::from([2u8; 32]);
+
+    let mut unkeyed = SMT::default();
+    unkeyed.update(key, value).expect("update");
+
+    let mut keyed =
+        SMT::with_hasher_key(H256::zero(), DefaultStore::default(), H256::from([9u8; 32]));
+    keyed.update(key, value).expect("update");
+
+    assert_ne!(unkeyed.root(), keyed.root());
+}
+
+#[cfg(feature = "blake2b")]
+#[test]
+fn hasher_key_changes_root() {
+    use crate::internal_blake2b::Blake2bHasher;
+
+    type KeyedSMT = SparseMerkleTree<Blake2bHasher, H256, H256, DefaultStore<H256, 32>, 32>;
+
+    let key = H256::from([1u8; 32]);
+    let value = H256::from([2u8; 32]);
+
+    let mut unkeyed = KeyedSMT::default();
+    unkeyed.update(key, value).expect("update");
+
+    let mut keyed = KeyedSMT::with_hasher_key(H256::zero(), DefaultStore::default(), H256::from([9u8; 32]));
+    keyed.update(key, value).expect("update");
+
+    assert_ne!(unkeyed.root(), keyed.root());
+}
+
+#[test]
+fn deleting_a_leaf_is_indistinguishable_from_absent() {
+    let mut written = SMT::default();
+    let key = H256::from([10u8; 32]);
+    written.update(key, H256::from([11u8; 32])).expect("update");
+    written.update(key, H256::zero()).expect("delete");
+
+    let never_written = SMT::default();
+
+    assert_eq!(written.get(&key).expect("get"), H256::zero());
+    assert_eq!(written.root(), never_written.root());
+}
+
+#[test]
+fn tree_validates_after_updates() {
+    let mut tree = SMT::default();
+    for i in 0u8..8 {
+        let mut key = [0u8; 32];
+        key[31] = i;
+        tree.update(key.into(), H256::from([i; 32])).expect("update");
+    }
+
+    assert!(tree.validate());
+}
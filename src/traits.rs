@@ -0,0 +1,84 @@
+//! Extension points a caller plugs into [`SparseMerkleTree`](crate::tree::SparseMerkleTree):
+//! the hash function, the leaf value type and the backing store.
+
+use crate::{error::Error, H256};
+
+/// A hash function over the tree's nodes and leaves.
+///
+/// Implementors accumulate bytes via [`write_bytes`](Hasher::write_bytes) and
+/// produce the final digest with [`finish`](Hasher::finish). See
+/// [`internal_blake2b::Blake2bHasher`](crate::internal_blake2b::Blake2bHasher) for a reference
+/// implementation.
+pub trait Hasher {
+    /// Feeds a single byte into the hasher.
+    fn write_byte(&mut self, b: u8) {
+        self.write_bytes(&[b]);
+    }
+
+    /// Feeds an `H256` into the hasher.
+    fn write_h256(&mut self, h: &H256) {
+        self.write_bytes(h.as_slice());
+    }
+
+    /// Feeds raw bytes into the hasher.
+    fn write_bytes(&mut self, bytes: &[u8]);
+
+    /// Consumes the hasher, producing the final digest.
+    fn finish(self) -> H256;
+
+    /// Builds a hasher bound to a domain-separation key, so a whole tree's
+    /// nodes can be computed under one tenant/application key without
+    /// changing the merge structure. Optional: hashers without a distinct
+    /// keyed mode can leave the default, which just falls back to
+    /// [`Default::default()`].
+    fn new_keyed(key: &H256) -> Self
+    where
+        Self: Default + Sized,
+    {
+        let _ = key;
+        Self::default()
+    }
+}
+
+/// The leaf value stored at each key of the tree.
+pub trait Value {
+    /// The value representing "nothing stored here", used to delete a leaf
+    /// and to fill empty subtrees.
+    fn zero() -> Self;
+
+    /// Byte representation that gets hashed to produce the value's digest.
+    fn as_slice(&self) -> &[u8];
+}
+
+/// Marker trait for types that can address a leaf in the tree.
+///
+/// A key only needs to be convertible into the tree's internal `H256`
+/// path; most callers simply use `H256`/`Hash` itself as their key
+/// type.
+pub trait Key: Into<H256> + Copy {}
+
+impl<T> Key for T where T: Into<H256> + Copy {}
+
+/// Backing storage for branch and leaf nodes.
+///
+/// `N` mirrors the tree height (in bytes) so a store implementation can size
+/// its on-disk/in-memory layout accordingly; `DefaultStore` ignores it.
+pub trait Store<V, const N: usize> {
+    /// Looks up the two children of a branch node, keyed by the branch's own hash.
+    fn get_branch(&self, node: &H256) -> Result<Option<(H256, H256)>, Error>;
+
+    /// Looks up the value stored at a leaf, keyed by the leaf's key.
+    fn get_leaf(&self, leaf_key: &H256) -> Result<Option<V>, Error>;
+
+    /// Records a branch node's children under its own hash.
+    fn insert_branch(&mut self, node: H256, left: H256, right: H256) -> Result<(), Error>;
+
+    /// Records a leaf's value under its key.
+    fn insert_leaf(&mut self, leaf_key: H256, value: V) -> Result<(), Error>;
+
+    /// Removes a branch node.
+    fn remove_branch(&mut self, node: &H256) -> Result<(), Error>;
+
+    /// Removes a leaf.
+    fn remove_leaf(&mut self, leaf_key: &H256) -> Result<(), Error>;
+}
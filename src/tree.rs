@@ -0,0 +1,451 @@
+//! The sparse Merkle tree itself: a 256-level binary tree where every
+//! key maps to a leaf and every unused subtree is implicitly all-zero.
+
+use core::marker::PhantomData;
+
+use crate::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    h256::H256,
+    merge,
+    merkle_proof::MerkleProof,
+    traits::{Hasher, Key, Store, Value},
+    vec::Vec,
+    TREE_HEIGHT,
+};
+
+/// A batch update's change set at one tree height: each touched node's new
+/// hash, paired with the original changed key later levels use to look up
+/// an unchanged sibling in `old_siblings`.
+type Level = BTreeMap<H256, (H256, H256)>;
+
+/// One partition's recomputed subtree in [`SparseMerkleTree::par_update_all`]:
+/// its root node and `(hash, representative)` entry to fold into the next
+/// level, plus the branch records it produced along the way.
+type PartitionResult = (H256, (H256, H256), Vec<(H256, H256, H256)>);
+
+/// A sparse Merkle tree over a 256-bit key space.
+///
+/// `H` is the hash function, `K` the caller-facing key type, `V` the leaf
+/// value type, `S` the backing [`Store`], and `N` the tree height in bytes
+/// (`32`, i.e. `TREE_HEIGHT / 8`).
+#[derive(Debug, Clone)]
+pub struct SparseMerkleTree<H, K, V, S, const N: usize> {
+    store: S,
+    root: H256,
+    /// Domain-separation key every node hash in this tree is computed
+    /// under, via [`Hasher::new_keyed`]. `None` uses `H::default()`, as a
+    /// tree without a configured hasher key always has.
+    hasher_key: Option<H256>,
+    phantom: PhantomData<(H, K, V)>,
+}
+
+impl<H, K, V, S: Default, const N: usize> Default for SparseMerkleTree<H, K, V, S, N> {
+    fn default() -> Self {
+        SparseMerkleTree {
+            store: S::default(),
+            root: H256::zero(),
+            hasher_key: None,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<H, K, V, S, const N: usize> SparseMerkleTree<H, K, V, S, N> {
+    /// Wraps an existing store and root, e.g. one loaded from persistent storage.
+    pub fn new(root: H256, store: S) -> Self {
+        SparseMerkleTree { store, root, hasher_key: None, phantom: PhantomData }
+    }
+
+    /// Same as [`new`](Self::new), but every node hash is computed under
+    /// `hasher_key` (see [`Hasher::new_keyed`]) instead of `H::default()`.
+    /// Use this so two trees backed by the same store, or the same
+    /// application, can't collide in the hash domain -- each tenant/context
+    /// gets its own key.
+    pub fn with_hasher_key(root: H256, store: S, hasher_key: H256) -> Self {
+        SparseMerkleTree { store, root, hasher_key: Some(hasher_key), phantom: PhantomData }
+    }
+
+    pub fn root(&self) -> &H256 {
+        &self.root
+    }
+
+    pub fn store(&self) -> &S {
+        &self.store
+    }
+
+    pub fn into_store(self) -> S {
+        self.store
+    }
+}
+
+impl<H, K, V, S, const N: usize> SparseMerkleTree<H, K, V, S, N>
+where
+    H: Hasher + Default,
+    K: Key,
+    V: Value + Clone,
+    S: Store<V, N>,
+{
+    /// Inserts or overwrites the value at `key`, rehashing the full path to
+    /// the root. Writing `V::zero()` deletes the leaf.
+    pub fn update(&mut self, key: K, value: V) -> Result<&H256, Error> {
+        let key: H256 = key.into();
+        let value_hash = merge::hash_value::<H, _>(self.hasher_key.as_ref(), &value);
+        let leaf_hash = merge::hash_leaf::<H>(self.hasher_key.as_ref(), &key, &value_hash);
+
+        // Walk down from the root, collecting the sibling at every height so
+        // the path can be rebuilt bottom-up with the new leaf in place.
+        let mut siblings = Vec::with_capacity(TREE_HEIGHT);
+        let mut node = self.root;
+        for height in (0..TREE_HEIGHT).rev().map(|h| h as u8) {
+            let (left, right) = if node.is_zero() {
+                (H256::zero(), H256::zero())
+            } else {
+                self.store.get_branch(&node)?.ok_or(Error::MissingBranch(height))?
+            };
+            let sibling = if key.get_bit(height) { left } else { right };
+            siblings.push(sibling);
+            node = if key.get_bit(height) { right } else { left };
+        }
+
+        self.store.insert_leaf(key, value)?;
+
+        let mut node = leaf_hash;
+        for (height, sibling) in (0..TREE_HEIGHT).map(|h| h as u8).zip(siblings.into_iter().rev()) {
+            let (left, right) = if key.get_bit(height) { (sibling, node) } else { (node, sibling) };
+            node = merge::merge::<H>(self.hasher_key.as_ref(), &left, &right);
+            if !node.is_zero() {
+                self.store.insert_branch(node, left, right)?;
+            }
+        }
+        self.root = node;
+        Ok(&self.root)
+    }
+
+    /// Applies every change in `changes` and returns the new root, touching
+    /// each shared ancestor's hash exactly once instead of re-walking its
+    /// full 256-level path once per key.
+    ///
+    /// This is the cached-tree-hash pattern: keys are grouped level by level
+    /// bottom-up into a per-level change set keyed by the ancestor they
+    /// share at that height (its `H256` path with that height's bit
+    /// cleared). At each level, a changed pair of siblings merges both new
+    /// hashes; a pair with only one changed side reads the other from the
+    /// pre-batch tree (precomputed once per key, not re-fetched per level);
+    /// an ancestor with no changed descendant never enters the change set
+    /// and so is never revisited. The result is exactly the root sequential
+    /// `update` calls would have produced.
+    pub fn update_all(&mut self, mut changes: Vec<(K, V)>) -> Result<&H256, Error> {
+        if changes.is_empty() {
+            return Ok(&self.root);
+        }
+        changes.sort_by_key(|(k, _)| (*k).into());
+
+        // For each distinct changed key, the sibling at every height along
+        // its path through the *pre-batch* tree -- the same walk `update`
+        // does, but run once up front so levels below never touch the store
+        // for a key whose sibling didn't change this batch.
+        let mut old_siblings: BTreeMap<H256, Vec<H256>> = BTreeMap::new();
+        for (key, _) in &changes {
+            let key: H256 = (*key).into();
+            if old_siblings.contains_key(&key) {
+                continue;
+            }
+            let mut siblings = Vec::with_capacity(TREE_HEIGHT);
+            let mut node = self.root;
+            for height in (0..TREE_HEIGHT).rev().map(|h| h as u8) {
+                let (left, right) = if node.is_zero() {
+                    (H256::zero(), H256::zero())
+                } else {
+                    self.store.get_branch(&node)?.ok_or(Error::MissingBranch(height))?
+                };
+                siblings.push(if key.get_bit(height) { left } else { right });
+                node = if key.get_bit(height) { right } else { left };
+            }
+            siblings.reverse();
+            old_siblings.insert(key, siblings);
+        }
+
+        // The height-0 change set: each changed key's new leaf hash, paired
+        // with itself as the "representative" original key later levels use
+        // to look up an unchanged sibling in `old_siblings`.
+        let mut level: Level = BTreeMap::new();
+        for (key, value) in changes {
+            let key: H256 = key.into();
+            let value_hash = merge::hash_value::<H, _>(self.hasher_key.as_ref(), &value);
+            let leaf_hash = merge::hash_leaf::<H>(self.hasher_key.as_ref(), &key, &value_hash);
+            level.insert(key, (leaf_hash, key));
+            self.store.insert_leaf(key, value)?;
+        }
+
+        for height in (0..TREE_HEIGHT).map(|h| h as u8) {
+            let (next_level, inserts) = merge_level::<H>(self.hasher_key.as_ref(), level, height, &old_siblings);
+            for (hash, left, right) in inserts {
+                self.store.insert_branch(hash, left, right)?;
+            }
+            level = next_level;
+        }
+
+        self.root = level.into_iter().next().map(|(_, (hash, _))| hash).unwrap_or_else(H256::zero);
+        Ok(&self.root)
+    }
+
+    /// Same as [`update_all`](Self::update_all), but spreads the bottom
+    /// `PARTITION_HEIGHT` levels of work across a rayon thread pool:
+    /// changes are grouped by their key's first byte into up to
+    /// `2.pow(PARTITION_BITS)` disjoint subtrees (disjoint because that byte
+    /// fixes every branching decision down to `PARTITION_HEIGHT`), each
+    /// subtree's levels are recomputed independently on a worker, and only
+    /// the handful of resulting subtree roots are merged the rest of the
+    /// way to the true root, single-threaded. Produces the exact same root
+    /// `update_all` would for the same `changes`.
+    #[cfg(feature = "rayon")]
+    pub fn par_update_all(&mut self, mut changes: Vec<(K, V)>) -> Result<&H256, Error>
+    where
+        H: Sync,
+        K: Send + Sync,
+        V: Send + Sync,
+        S: Sync,
+    {
+        if changes.is_empty() {
+            return Ok(&self.root);
+        }
+
+        use rayon::prelude::*;
+
+        /// How many of a key's top bits partition work across the thread
+        /// pool. Every key sharing these bits shares every branch from the
+        /// root down to `PARTITION_HEIGHT`, so partitions never conflict.
+        const PARTITION_BITS: usize = 8;
+        const PARTITION_HEIGHT: u8 = (TREE_HEIGHT - PARTITION_BITS) as u8;
+
+        changes.sort_by_key(|(k, _)| (*k).into());
+        let hasher_key = self.hasher_key;
+
+        let distinct_keys: BTreeSet<H256> = changes.iter().map(|(k, _)| (*k).into()).collect();
+        let old_siblings: BTreeMap<H256, Vec<H256>> = distinct_keys
+            .into_par_iter()
+            .map(|key| -> Result<(H256, Vec<H256>), Error> {
+                let mut siblings = Vec::with_capacity(TREE_HEIGHT);
+                let mut node = self.root;
+                for height in (0..TREE_HEIGHT).rev().map(|h| h as u8) {
+                    let (left, right) = if node.is_zero() {
+                        (H256::zero(), H256::zero())
+                    } else {
+                        self.store.get_branch(&node)?.ok_or(Error::MissingBranch(height))?
+                    };
+                    siblings.push(if key.get_bit(height) { left } else { right });
+                    node = if key.get_bit(height) { right } else { left };
+                }
+                siblings.reverse();
+                Ok((key, siblings))
+            })
+            .collect::<Result<_, Error>>()?;
+
+        // Leaf/value hashing is embarrassingly parallel; only the store
+        // writes that follow need to stay single-threaded.
+        let hashed: Vec<(H256, V, H256)> = changes
+            .into_par_iter()
+            .map(|(key, value)| {
+                let key: H256 = key.into();
+                let value_hash = merge::hash_value::<H, _>(hasher_key.as_ref(), &value);
+                let leaf_hash = merge::hash_leaf::<H>(hasher_key.as_ref(), &key, &value_hash);
+                (key, value, leaf_hash)
+            })
+            .collect();
+
+        let mut partitions: BTreeMap<u8, Level> = BTreeMap::new();
+        for (key, value, leaf_hash) in hashed {
+            self.store.insert_leaf(key, value)?;
+            let partition = key.as_slice()[0];
+            partitions.entry(partition).or_default().insert(key, (leaf_hash, key));
+        }
+
+        // Each partition recomputes its own disjoint subtree, from the
+        // leaves up to `PARTITION_HEIGHT`, entirely independently of the
+        // others -- this is the parallel part.
+        let partition_results: Vec<PartitionResult> = partitions
+            .into_par_iter()
+            .map(|(_, mut bucket_level)| {
+                let mut inserts = Vec::new();
+                for height in 0..PARTITION_HEIGHT {
+                    let (next_level, bucket_inserts) =
+                        merge_level::<H>(hasher_key.as_ref(), bucket_level, height, &old_siblings);
+                    inserts.extend(bucket_inserts);
+                    bucket_level = next_level;
+                }
+                // A partition sharing the same top byte always converges to
+                // exactly one node by `PARTITION_HEIGHT`.
+                let (node, entry) = bucket_level.into_iter().next().expect("non-empty partition");
+                (node, entry, inserts)
+            })
+            .collect();
+
+        // Only a handful of partition roots remain; merge them the rest of
+        // the way to the true root single-threaded.
+        let mut level = BTreeMap::new();
+        for (node, entry, inserts) in partition_results {
+            for (hash, left, right) in inserts {
+                self.store.insert_branch(hash, left, right)?;
+            }
+            level.insert(node, entry);
+        }
+
+        for height in PARTITION_HEIGHT..(TREE_HEIGHT as u8) {
+            let (next_level, inserts) = merge_level::<H>(hasher_key.as_ref(), level, height, &old_siblings);
+            for (hash, left, right) in inserts {
+                self.store.insert_branch(hash, left, right)?;
+            }
+            level = next_level;
+        }
+
+        self.root = level.into_iter().next().map(|(_, (hash, _))| hash).unwrap_or_else(H256::zero);
+        Ok(&self.root)
+    }
+
+    /// Fetches the value stored at `key`, or `V::zero()` if the leaf is empty.
+    pub fn get(&self, key: &K) -> Result<V, Error> {
+        let key: H256 = (*key).into();
+        match self.store.get_leaf(&key)? {
+            Some(value) => Ok(value),
+            None => Ok(V::zero()),
+        }
+    }
+
+    /// Generates an inclusion proof for `keys`, sorted internally by their
+    /// `H256` representation.
+    pub fn merkle_proof(&self, mut keys: Vec<K>) -> Result<MerkleProof, Error> {
+        keys.sort_by_key(|k| (*k).into());
+
+        let mut paths = Vec::with_capacity(keys.len());
+        for key in keys {
+            let key: H256 = key.into();
+            let mut path = Vec::with_capacity(TREE_HEIGHT);
+            let mut node = self.root;
+            for height in (0..TREE_HEIGHT).rev().map(|h| h as u8) {
+                let (left, right) = if node.is_zero() {
+                    (H256::zero(), H256::zero())
+                } else {
+                    self.store.get_branch(&node)?.ok_or(Error::MissingBranch(height))?
+                };
+                path.push(if key.get_bit(height) { left } else { right });
+                node = if key.get_bit(height) { right } else { left };
+            }
+            path.reverse();
+            paths.push(path);
+        }
+
+        Ok(MerkleProof::new(paths))
+    }
+
+    /// Same as [`merkle_proof`](Self::merkle_proof), but generates each
+    /// key's sibling path concurrently on a rayon thread pool before
+    /// assembling the combined proof. Produces identical output for the
+    /// same `keys`.
+    #[cfg(feature = "rayon")]
+    pub fn par_merkle_proof(&self, mut keys: Vec<K>) -> Result<MerkleProof, Error>
+    where
+        H: Sync,
+        K: Send + Sync,
+        V: Sync,
+        S: Sync,
+    {
+        use rayon::prelude::*;
+
+        keys.sort_by_key(|k| (*k).into());
+
+        let paths: Vec<Vec<H256>> = keys
+            .into_par_iter()
+            .map(|key| -> Result<Vec<H256>, Error> {
+                let key: H256 = key.into();
+                let mut path = Vec::with_capacity(TREE_HEIGHT);
+                let mut node = self.root;
+                for height in (0..TREE_HEIGHT).rev().map(|h| h as u8) {
+                    let (left, right) = if node.is_zero() {
+                        (H256::zero(), H256::zero())
+                    } else {
+                        self.store.get_branch(&node)?.ok_or(Error::MissingBranch(height))?
+                    };
+                    path.push(if key.get_bit(height) { left } else { right });
+                    node = if key.get_bit(height) { right } else { left };
+                }
+                path.reverse();
+                Ok(path)
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(MerkleProof::new(paths))
+    }
+
+    /// Walks the tree from the root, checking that every branch's recorded
+    /// children merge back to the hash it is stored under. Intended for
+    /// tests and debugging, not the hot update path.
+    pub fn validate(&self) -> bool {
+        self.validate_node(self.root)
+    }
+
+    fn validate_node(&self, node: H256) -> bool {
+        if node.is_zero() {
+            return true;
+        }
+        match self.store.get_branch(&node) {
+            Ok(Some((left, right))) => {
+                merge::merge::<H>(self.hasher_key.as_ref(), &left, &right) == node
+                    && self.validate_node(left)
+                    && self.validate_node(right)
+            }
+            // A non-zero node with no recorded children is a leaf hash; leaves
+            // have nothing further to validate structurally.
+            _ => true,
+        }
+    }
+}
+
+/// Merges one level of a batch update's change set into the next: every
+/// changed node at `height` is paired with its sibling (either another
+/// changed node already in `level`, or the unchanged sibling recorded in
+/// `old_siblings`) and merged into its parent's hash.
+///
+/// Pure with respect to the store -- branch nodes to record are returned
+/// rather than written, so this can run independently of `&mut self` on a
+/// worker thread (see [`SparseMerkleTree::par_update_all`]).
+fn merge_level<H: Hasher + Default>(
+    hasher_key: Option<&H256>,
+    level: Level,
+    height: u8,
+    old_siblings: &BTreeMap<H256, Vec<H256>>,
+) -> (Level, Vec<(H256, H256, H256)>) {
+    let mut next_level = BTreeMap::new();
+    let mut inserts = Vec::new();
+    let mut seen_parents = BTreeSet::new();
+
+    for (&node, &(new_hash, representative)) in level.iter() {
+        let mut parent = node;
+        parent.clear_bit(height);
+        if !seen_parents.insert(parent) {
+            continue;
+        }
+
+        let mut sibling = node;
+        if node.get_bit(height) {
+            sibling.clear_bit(height);
+        } else {
+            sibling.set_bit(height);
+        }
+
+        let (left, right) = if let Some(&(sibling_hash, _)) = level.get(&sibling) {
+            if node.get_bit(height) { (sibling_hash, new_hash) } else { (new_hash, sibling_hash) }
+        } else {
+            let sibling_hash = old_siblings[&representative][height as usize];
+            if node.get_bit(height) { (sibling_hash, new_hash) } else { (new_hash, sibling_hash) }
+        };
+
+        let parent_hash = merge::merge::<H>(hasher_key, &left, &right);
+        if !parent_hash.is_zero() {
+            inserts.push((parent_hash, left, right));
+        }
+        next_level.insert(parent, (parent_hash, representative));
+    }
+
+    (next_level, inserts)
+}